@@ -0,0 +1,299 @@
+//! A minimal structural diff that emits text edits, so callers who rebuild a
+//! tree after an edit can produce a tight set of byte-range replacements rather
+//! than replacing the whole document.
+
+use crate::red::SyntaxNode2D;
+use crate::syntax::Syntax2D;
+use cstree::{
+    Syntax,
+    green::{GreenNode, GreenToken},
+    interning::Resolver,
+    text::{TextRange, TextSize},
+    util::NodeOrToken,
+};
+
+/**************************************************************/
+
+/// A single byte-range replacement in the old document's rendered text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The range in the old text to replace (empty range for a pure insertion).
+    pub range: TextRange,
+    /// The text to insert in its place (empty for a pure deletion).
+    pub insert: String,
+}
+
+/// Computes a minimal set of text edits turning `old` into `new`.
+///
+/// The diff is top-down and structural: if two aligned nodes differ in
+/// `Syntax` kind, one replacement of the old node's text range with the new
+/// node's rendered text is emitted and the walk stops descending; if the kinds
+/// match, children are aligned by an LCS over their kind keys and the walk
+/// recurses into matched node pairs; matched token pairs whose text differs emit
+/// a replacement, and unmatched runs emit insert/delete edits.
+/// `Indent`/`Dedent`/`Newline` tokens take part in the alignment like any other
+/// element, so whitespace-only changes produce tight edits too.
+///
+/// Ranges and inserted strings are both measured in the *rendered* (indentation-
+/// expanded) text — the same byte space as [`crate::text::SyntaxText2D`] — so an
+/// edit can be applied directly to `old.to_string()`.
+pub fn diff<S: Syntax>(old: &SyntaxNode2D<S>, new: &SyntaxNode2D<S>) -> Vec<TextEdit> {
+    let old_resolver = old.inner().resolver().map(|r| &**r);
+    let new_resolver = new.inner().resolver().map(|r| &**r);
+    let mut edits = Vec::new();
+    let mut cursor = 0usize;
+    let mut ctx = RenderCtx::default();
+    diff_node::<S, _>(
+        old.green(),
+        new.green(),
+        &mut cursor,
+        &mut ctx,
+        old_resolver,
+        new_resolver,
+        &mut edits,
+    );
+    edits
+}
+
+/// The indentation state of the rendering stack machine, mirroring
+/// [`crate::green::extract_text`]: the live indent strings and whether a newly
+/// opened line still owes its indentation.
+#[derive(Default, Clone)]
+struct RenderCtx {
+    stack: Vec<String>,
+    pending: bool,
+}
+
+/// Diffs two aligned green nodes, advancing `cursor` across the old node's
+/// rendered text and keeping `ctx` in sync with that walk.
+#[allow(clippy::too_many_arguments)]
+fn diff_node<S: Syntax, R: Resolver + ?Sized>(
+    old: &GreenNode,
+    new: &GreenNode,
+    cursor: &mut usize,
+    ctx: &mut RenderCtx,
+    old_resolver: Option<&R>,
+    new_resolver: Option<&R>,
+    edits: &mut Vec<TextEdit>,
+) {
+    if old.kind() != new.kind() {
+        let start = *cursor;
+        let before = ctx.clone();
+        let mut old_text = String::new();
+        emit_element::<S, R>(&NodeOrToken::Node(old.clone()), old_resolver, ctx, &mut old_text);
+        *cursor += old_text.len();
+        let mut insert = String::new();
+        emit_element::<S, R>(
+            &NodeOrToken::Node(new.clone()),
+            new_resolver,
+            &mut before.clone(),
+            &mut insert,
+        );
+        edits.push(TextEdit {
+            range: range(start, *cursor),
+            insert,
+        });
+        return;
+    }
+
+    let olds: Vec<_> = old.children().map(owned).collect();
+    let news: Vec<_> = new.children().map(owned).collect();
+    for op in lcs(&olds, &news) {
+        match op {
+            Op::Match(oi, ni) => match (&olds[oi], &news[ni]) {
+                (NodeOrToken::Node(on), NodeOrToken::Node(nn)) => {
+                    diff_node::<S, R>(on, nn, cursor, ctx, old_resolver, new_resolver, edits)
+                }
+                (NodeOrToken::Token(ot), NodeOrToken::Token(nt)) => {
+                    // Same kind, so the LCS aligned them, but the text may still
+                    // differ (e.g. `Text "cat"` -> `Text "dog"`): emit a tight
+                    // replacement instead of silently dropping the change.
+                    let start = *cursor;
+                    let before = ctx.clone();
+                    let mut old_text = String::new();
+                    emit_token::<S, R>(ot, old_resolver, ctx, &mut old_text);
+                    *cursor += old_text.len();
+                    // Compare resolved text: token keys from two interners are
+                    // not comparable.
+                    if token_text(ot, old_resolver) != token_text(nt, new_resolver) {
+                        let mut insert = String::new();
+                        emit_token::<S, R>(nt, new_resolver, &mut before.clone(), &mut insert);
+                        edits.push(TextEdit {
+                            range: range(start, *cursor),
+                            insert,
+                        });
+                    }
+                }
+                _ => {
+                    let mut old_text = String::new();
+                    emit_element::<S, R>(&olds[oi], old_resolver, ctx, &mut old_text);
+                    *cursor += old_text.len();
+                }
+            },
+            Op::Delete(oi) => {
+                let start = *cursor;
+                let mut old_text = String::new();
+                emit_element::<S, R>(&olds[oi], old_resolver, ctx, &mut old_text);
+                *cursor += old_text.len();
+                edits.push(TextEdit {
+                    range: range(start, *cursor),
+                    insert: String::new(),
+                });
+            }
+            Op::Insert(ni) => {
+                let mut insert = String::new();
+                emit_element::<S, R>(&news[ni], new_resolver, &mut ctx.clone(), &mut insert);
+                edits.push(TextEdit {
+                    range: range(*cursor, *cursor),
+                    insert,
+                });
+            }
+        }
+    }
+}
+
+/// Renders `elem` into `out` exactly as [`crate::green::extract_text`] would,
+/// advancing `ctx` through the indentation stack machine.
+fn emit_element<S: Syntax, R: Resolver + ?Sized>(
+    elem: &Element,
+    resolver: Option<&R>,
+    ctx: &mut RenderCtx,
+    out: &mut String,
+) {
+    match elem {
+        NodeOrToken::Node(node) => {
+            for child in node.children() {
+                emit_element::<S, R>(&owned(child), resolver, ctx, out);
+            }
+        }
+        NodeOrToken::Token(token) => emit_token::<S, R>(token, resolver, ctx, out),
+    }
+}
+
+/// Renders a single token into `out`, updating the indentation stack machine.
+fn emit_token<S: Syntax, R: Resolver + ?Sized>(
+    token: &GreenToken,
+    resolver: Option<&R>,
+    ctx: &mut RenderCtx,
+    out: &mut String,
+) {
+    match Syntax2D::<S>::from_raw(token.kind()) {
+        Syntax2D::Indent => {
+            ctx.stack.push(token_text(token, resolver));
+            ctx.pending = true;
+        }
+        Syntax2D::Dedent => {
+            ctx.stack.pop();
+        }
+        Syntax2D::Newline => {
+            if ctx.pending {
+                for indent in &ctx.stack {
+                    out.push_str(indent);
+                }
+            }
+            out.push('\n');
+            ctx.pending = !ctx.stack.is_empty();
+        }
+        Syntax2D::Token(_) => {
+            if ctx.pending {
+                for indent in &ctx.stack {
+                    out.push_str(indent);
+                }
+                ctx.pending = false;
+            }
+            out.push_str(&token_text(token, resolver));
+        }
+    }
+}
+
+/// The interned text of `token`, or the empty string if it has no resolver.
+fn token_text<R: Resolver + ?Sized>(token: &GreenToken, resolver: Option<&R>) -> String {
+    resolver
+        .and_then(|r| token.text(r))
+        .unwrap_or("")
+        .to_string()
+}
+
+/**************************************************************/
+
+type Element = NodeOrToken<GreenNode, GreenToken>;
+
+/// An alignment step between the old and new child sequences.
+enum Op {
+    Match(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Aligns two element sequences by a longest-common-subsequence of their keys.
+fn lcs(old: &[Element], new: &[Element]) -> Vec<Op> {
+    let a: Vec<Key> = old.iter().map(key).collect();
+    let b: Vec<Key> = new.iter().map(key).collect();
+    let (n, m) = (a.len(), b.len());
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            ops.push(Op::Match(i, j));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            ops.push(Op::Delete(i));
+            i += 1;
+        } else {
+            ops.push(Op::Insert(j));
+            j += 1;
+        }
+    }
+    while i < n {
+        ops.push(Op::Delete(i));
+        i += 1;
+    }
+    while j < m {
+        ops.push(Op::Insert(j));
+        j += 1;
+    }
+    ops
+}
+
+/// The alignment key for an element.
+///
+/// Nodes and tokens are kept in distinct variants so a node never aligns with a
+/// token, and elements align by raw kind only. Keying by kind (rather than by
+/// text length) lets two tokens of the same kind but different text align, so
+/// the diff recurses into same-kind node pairs and reports text changes on
+/// matched token pairs instead of treating equal-length tokens as unchanged.
+#[derive(PartialEq, Eq)]
+enum Key {
+    Node(u32),
+    Token(u32),
+}
+
+fn key(elem: &Element) -> Key {
+    match elem {
+        NodeOrToken::Node(n) => Key::Node(n.kind().0),
+        NodeOrToken::Token(t) => Key::Token(t.kind().0),
+    }
+}
+
+fn owned(elem: NodeOrToken<&GreenNode, &GreenToken>) -> Element {
+    match elem {
+        NodeOrToken::Node(n) => NodeOrToken::Node(n.clone()),
+        NodeOrToken::Token(t) => NodeOrToken::Token(t.clone()),
+    }
+}
+
+fn range(start: usize, end: usize) -> TextRange {
+    TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32))
+}
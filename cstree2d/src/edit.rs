@@ -0,0 +1,417 @@
+use crate::{red::ResolvedNode2D, syntax::Syntax2D};
+use cstree::{
+    Syntax,
+    build::GreenNodeBuilder,
+    green::{GreenNode, GreenToken},
+    interning::{Resolver, TokenKey},
+    syntax::{ResolvedNode, SyntaxNode},
+    util::NodeOrToken,
+};
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::sync::Arc;
+
+/**************************************************************/
+
+/// Re-attaches the resolver shared by an existing tree to a freshly rooted one.
+///
+/// `SyntaxNode::resolver` hands back an `Arc<dyn Resolver>`, but
+/// `ResolvedNode::new_root_with_resolver` wants an owned `impl Resolver` and
+/// cstree does not implement `Resolver` for the `Arc`. This newtype forwards the
+/// single lookup method so the shared resolver can be cloned onto the rebuilt
+/// tree without re-interning every token.
+#[derive(Clone)]
+pub(crate) struct SharedResolver(Arc<dyn Resolver<TokenKey>>);
+
+impl SharedResolver {
+    /// Clones the resolver shared by `node`'s tree.
+    pub(crate) fn of<S: Syntax>(node: &ResolvedNode2D<S>) -> SharedResolver {
+        SharedResolver(node.inner().resolver().clone())
+    }
+}
+
+impl Resolver<TokenKey> for SharedResolver {
+    fn try_resolve(&self, key: TokenKey) -> Option<&str> {
+        self.0.try_resolve(key)
+    }
+}
+
+/**************************************************************/
+
+/// The default indentation unit: four spaces.
+const INDENT_UNIT: &str = "    ";
+
+/// A node's indentation depth, measured in indentation units.
+///
+/// `Display` emits the default unit (four spaces) repeated `level` times, so
+/// `format!("{}", IndentLevel(2))` yields eight spaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IndentLevel(pub u32);
+
+impl IndentLevel {
+    /// Computes a node's current depth from the net `Indent`/`Dedent` balance on
+    /// the path from the root down to it.
+    pub fn of<S: Syntax>(node: &ResolvedNode2D<S>) -> IndentLevel {
+        let path = path_from_root(node.inner());
+        let mut stack = Vec::new();
+        collect_indent_stack(root_green(node.inner()), &path, 0, &mut stack);
+        IndentLevel(stack.len() as u32)
+    }
+}
+
+impl Display for IndentLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for _ in 0..self.0 {
+            f.write_str(INDENT_UNIT)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Syntax> ResolvedNode2D<S> {
+    /// Returns a fresh tree whose leading indentation has been normalized to
+    /// `level`, shifting the whole subtree left or right while preserving the
+    /// relative nesting inside it.
+    ///
+    /// The rewrite reuses an existing `Indent` token as the unit, so no new
+    /// text is interned; the leading `Indent` tokens are replaced by exactly
+    /// `level` of them (balanced by matching `Dedent`s). If the tree holds no
+    /// `Indent` or no `Dedent` token to reuse, no indentation is added.
+    pub fn reindent_to(&self, level: IndentLevel) -> Self {
+        let root = root_green(self.inner());
+        let unit = first_indent(root);
+        let dedent = find_dedent(root);
+
+        let mut children: Vec<GreenElement> = self.green().children().map(clone_element).collect();
+
+        // Drop the existing leading run of `Indent` tokens and an equal number
+        // of trailing `Dedent`s, then re-home the subtree at the new depth.
+        let leading = children
+            .iter()
+            .take_while(|c| matches!(c, NodeOrToken::Token(t) if t.kind().0 == u32::MAX - 2))
+            .count();
+        children.drain(0..leading);
+        for _ in 0..leading {
+            if matches!(children.last(), Some(NodeOrToken::Token(t)) if t.kind().0 == u32::MAX - 1) {
+                children.pop();
+            }
+        }
+
+        if let (Some(unit), Some(dedent)) = (unit, dedent) {
+            for _ in 0..level.0 {
+                children.insert(0, NodeOrToken::Token(unit.clone()));
+            }
+            for _ in 0..level.0 {
+                children.push(NodeOrToken::Token(dedent.clone()));
+            }
+        }
+
+        let new_self = GreenNode::new(self.green().kind(), children);
+        let path = path_from_root(self.inner());
+        let new_root = rebuild_spine(root, &path, new_self);
+        ResolvedNode2D::new(ResolvedNode::new_root_with_resolver(
+            new_root,
+            SharedResolver::of(self),
+        ))
+    }
+}
+
+/// Finds the first `Indent` token in `node`, to reuse as the indentation unit.
+pub(crate) fn first_indent(node: &GreenNode) -> Option<GreenToken> {
+    for child in node.children() {
+        match child {
+            NodeOrToken::Token(t) if t.kind().0 == u32::MAX - 2 => return Some(t.clone()),
+            NodeOrToken::Node(n) => {
+                if let Some(t) = first_indent(n) {
+                    return Some(t);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/**************************************************************/
+
+/// A green element, either a node or a token, as stored in a `GreenNode`'s
+/// child list.
+pub(crate) type GreenElement = NodeOrToken<GreenNode, GreenToken>;
+
+/// Where to splice a freshly-built subtree into an existing node's children.
+///
+/// `Before`/`After` index into the node's child list, counting both nodes and
+/// tokens (the same numbering used by `GreenNode::children`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertPosition {
+    /// Before all existing children.
+    First,
+    /// After all existing children.
+    Last,
+    /// Immediately before the child at the given index.
+    Before(usize),
+    /// Immediately after the child at the given index.
+    After(usize),
+}
+
+impl InsertPosition {
+    /// Resolves this position to an insertion index into a child list of the
+    /// given length.
+    pub(crate) fn resolve(self, len: usize) -> usize {
+        match self {
+            InsertPosition::First => 0,
+            InsertPosition::Last => len,
+            InsertPosition::Before(i) => i,
+            InsertPosition::After(i) => i + 1,
+        }
+    }
+}
+
+/**************************************************************/
+
+/// Immutable structured editing: every method returns a fresh tree rather than
+/// mutating in place, reusing all untouched children by `Arc`-cloning and
+/// rebuilding only the spine from the edited node up to the root.
+///
+/// When a subtree is spliced in, its token stream is wrapped so that the
+/// `Indent`/`Dedent` tokens active at the insertion point are respected: an
+/// inserted block adopts the surrounding indentation depth, so its inner
+/// `Newline`s render at that depth instead of at column zero.
+impl<S: Syntax> ResolvedNode2D<S> {
+    /// Returns a fresh tree with `subtree` inserted among this node's children
+    /// at `position`, re-homed to the surrounding indentation depth.
+    pub fn insert_child(&self, position: InsertPosition, subtree: &ResolvedNode2D<S>) -> Self {
+        let children: Vec<GreenElement> = self.green().children().map(clone_element).collect();
+        let at = position.resolve(children.len());
+        self.splice_elements(at..at, std::iter::once(subtree.green().clone()))
+    }
+
+    /// Returns a fresh tree with the child element (node or token) at `index`
+    /// replaced by `subtree`, re-homed to the surrounding indentation depth.
+    pub fn replace_child(&self, index: usize, subtree: &ResolvedNode2D<S>) -> Self {
+        self.splice_elements(index..index + 1, std::iter::once(subtree.green().clone()))
+    }
+
+    /// Returns a fresh tree with the child elements in `range` replaced by the
+    /// given `subtrees`, each re-homed to the surrounding indentation depth.
+    pub fn splice_children<'a, I>(&self, range: Range<usize>, subtrees: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ResolvedNode2D<S>>,
+        S: 'a,
+    {
+        self.splice_elements(range, subtrees.into_iter().map(|s| s.green().clone()))
+    }
+
+    /// Shared splice implementation: replaces `range` of this node's children
+    /// with the wrapped `subtrees`, then rebuilds the spine to the root.
+    fn splice_elements(
+        &self,
+        range: Range<usize>,
+        subtrees: impl IntoIterator<Item = GreenNode>,
+    ) -> Self {
+        let path = path_from_root(self.inner());
+
+        // Reconstruct the indentation stack that is active when the walk enters
+        // this node, so inserted blocks can be re-homed to that depth.
+        let root = root_green(self.inner());
+        let mut stack = Vec::new();
+        collect_indent_stack(root, &path, 0, &mut stack);
+
+        let mut children: Vec<GreenElement> = self.green().children().map(clone_element).collect();
+        let wrapped: Vec<GreenElement> = subtrees
+            .into_iter()
+            .map(|subtree| reindent_subtree::<S>(subtree, &stack))
+            .collect();
+        children.splice(range, wrapped);
+
+        let new_self = GreenNode::new(self.green().kind(), children);
+        let new_root = rebuild_spine(root, &path, new_self);
+
+        ResolvedNode2D::new(ResolvedNode::new_root_with_resolver(
+            new_root,
+            SharedResolver::of(self),
+        ))
+    }
+}
+
+/// Re-homes a freshly-built subtree from its own base depth to the surrounding
+/// depth so its inner `Newline`s render at the insertion point's indentation.
+///
+/// Only the *delta* relative to the subtree's own leading `Indent` run is
+/// emitted — re-pushing the whole active stack would render the contents at
+/// roughly twice the intended depth. The added `Indent` tokens are `Arc`-clones
+/// of the enclosing context's own indent tokens (no new text is interned) and
+/// are always balanced by an equal number of `Dedent`s so the stack does not
+/// leak onto the following siblings.
+fn reindent_subtree<S: Syntax>(subtree: GreenNode, stack: &[GreenToken]) -> GreenElement {
+    let target = stack.len();
+    let base = leading_indents(&subtree);
+    if target == base {
+        return NodeOrToken::Node(subtree);
+    }
+    if target > base {
+        // Deeper destination: wrap the block in the extra enclosing `Indent`s
+        // (and balancing `Dedent`s) so its inner `Newline`s render one step
+        // further in for each level added.
+        let mut elements: Vec<GreenElement> = Vec::new();
+        for indent in &stack[base..target] {
+            elements.push(NodeOrToken::Token(indent.clone()));
+        }
+        elements.push(NodeOrToken::Node(subtree.clone()));
+        for _ in base..target {
+            elements.push(NodeOrToken::Token(static_dedent::<S>()));
+        }
+        NodeOrToken::Node(GreenNode::new(subtree.kind(), elements))
+    } else {
+        // Shallower destination: drop the block's own surplus leading `Indent`s
+        // and the matching trailing `Dedent`s from inside the block. Wrapping it
+        // in `Dedent`s instead would pop the *surrounding* context's indentation
+        // before the block rendered, corrupting the siblings that follow.
+        let surplus = base - target;
+        let mut children: Vec<GreenElement> = subtree.children().map(clone_element).collect();
+        children.drain(0..surplus);
+        for _ in 0..surplus {
+            if matches!(children.last(), Some(NodeOrToken::Token(t)) if t.kind().0 == u32::MAX - 1) {
+                children.pop();
+            }
+        }
+        NodeOrToken::Node(GreenNode::new(subtree.kind(), children))
+    }
+}
+
+/// The number of leading `Indent` tokens on `node`'s own children, i.e. the
+/// depth the subtree already carries before being re-homed.
+pub(crate) fn leading_indents(node: &GreenNode) -> usize {
+    node.children()
+        .take_while(|c| matches!(c, NodeOrToken::Token(t) if t.kind().0 == u32::MAX - 2))
+        .count()
+}
+
+/// Builds a standalone `Dedent` token through a throwaway builder. `Dedent` is a
+/// static, empty token, so it carries no interned text and composes with green
+/// trees built under any interner.
+fn static_dedent<S: Syntax>() -> GreenToken {
+    let mut builder: GreenNodeBuilder<Syntax2D<S>> = GreenNodeBuilder::new();
+    builder.start_node(Syntax2D::Dedent);
+    builder.static_token(Syntax2D::Dedent);
+    builder.finish_node();
+    let (green, _) = builder.finish();
+    match green.children().next() {
+        Some(NodeOrToken::Token(t)) => t.clone(),
+        _ => unreachable!("builder emitted exactly one Dedent token"),
+    }
+}
+
+/// Finds any `Dedent` token in `node` so it can be cloned for balancing.
+pub(crate) fn find_dedent(node: &GreenNode) -> Option<GreenToken> {
+    for child in node.children() {
+        match child {
+            NodeOrToken::Token(t) if t.kind().0 == u32::MAX - 1 => return Some(t.clone()),
+            NodeOrToken::Node(n) => {
+                if let Some(t) = find_dedent(n) {
+                    return Some(t);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/**************************************************************/
+
+/// Clones a borrowed green child into an owned element (cheap `Arc` bumps).
+pub(crate) fn clone_element(child: NodeOrToken<&GreenNode, &GreenToken>) -> GreenElement {
+    match child {
+        NodeOrToken::Node(n) => NodeOrToken::Node(n.clone()),
+        NodeOrToken::Token(t) => NodeOrToken::Token(t.clone()),
+    }
+}
+
+/// The child indices leading from the root down to `node` (root first).
+///
+/// Shared by the editing, `ted`, and indentation modules; `ResolvedNode`
+/// derefs to `SyntaxNode`, so resolver-backed callers pass their inner node
+/// directly.
+pub(crate) fn path_from_root<S: Syntax>(node: &SyntaxNode<Syntax2D<S>>) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut cur = node;
+    while let Some(parent) = cur.parent() {
+        path.push(child_index(parent, cur));
+        cur = parent;
+    }
+    path.reverse();
+    path
+}
+
+/// The index of `child` within `parent`'s element list, counting both nodes and
+/// tokens (the numbering `GreenNode::children` uses).
+///
+/// cstree's `SyntaxNode` has no `index()` in 0.12, so we locate the child by its
+/// text range, which is unique among siblings.
+fn child_index<S: Syntax>(
+    parent: &SyntaxNode<Syntax2D<S>>,
+    child: &SyntaxNode<Syntax2D<S>>,
+) -> usize {
+    let range = child.text_range();
+    parent
+        .children_with_tokens()
+        .position(|elem| elem.as_node().is_some() && elem.text_range() == range)
+        .expect("child must appear in its parent's element list")
+}
+
+/// The green node at the root of `node`'s tree.
+pub(crate) fn root_green<S: Syntax>(node: &SyntaxNode<Syntax2D<S>>) -> &GreenNode {
+    let mut cur = node;
+    while let Some(parent) = cur.parent() {
+        cur = parent;
+    }
+    cur.green()
+}
+
+/// Rebuilds the spine from the root down to the node at `path`, substituting
+/// `new_node` there and `Arc`-cloning every untouched sibling.
+pub(crate) fn rebuild_spine(node: &GreenNode, path: &[usize], new_node: GreenNode) -> GreenNode {
+    let (&idx, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return new_node,
+    };
+    let mut children: Vec<GreenElement> = node.children().map(clone_element).collect();
+    if let NodeOrToken::Node(child) = &children[idx] {
+        let rebuilt = rebuild_spine(child, rest, new_node);
+        children[idx] = NodeOrToken::Node(rebuilt);
+    }
+    GreenNode::new(node.kind(), children)
+}
+
+/// Follows `path` down to its node, accumulating the `Indent`/`Dedent` tokens
+/// that are direct children of each ancestor and precede the path. The contents
+/// of preceding sibling nodes are not walked.
+pub(crate) fn collect_indent_stack(
+    node: &GreenNode,
+    path: &[usize],
+    depth: usize,
+    stack: &mut Vec<GreenToken>,
+) -> bool {
+    for (i, child) in node.children().enumerate() {
+        if depth < path.len() && i == path[depth] {
+            if depth + 1 == path.len() {
+                return true;
+            }
+            if let NodeOrToken::Node(n) = child {
+                if collect_indent_stack(n, path, depth + 1, stack) {
+                    return true;
+                }
+            }
+        } else if let NodeOrToken::Token(t) = child {
+            match t.kind().0 {
+                x if x == u32::MAX - 2 => stack.push(t.clone()),
+                x if x == u32::MAX - 1 => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    false
+}
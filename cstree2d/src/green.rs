@@ -17,6 +17,13 @@ use std::fmt::Formatter;
 /// managing indentation tokens.
 pub struct Builder<'cache, 'interner, S: Syntax, I: Interner = TokenInterner> {
     inner: GreenNodeBuilder<'cache, 'interner, Syntax2D<S>, I>,
+    /// Raw kinds that open an indent scope (see [`Builder::with_indent_scopes`]).
+    indent_scopes: Vec<u32>,
+    /// The indentation unit auto-emitted for scoped nodes.
+    indent_unit: String,
+    /// Whether each currently-open node auto-emitted an `Indent`, so that
+    /// `finish_node` knows to emit the matching `Dedent`.
+    scope_stack: Vec<bool>,
 }
 
 impl<S: Syntax> Builder<'static, 'static, S> {
@@ -24,6 +31,27 @@ impl<S: Syntax> Builder<'static, 'static, S> {
     pub fn new() -> Self {
         Self {
             inner: GreenNodeBuilder::new(),
+            indent_scopes: Vec::new(),
+            indent_unit: String::new(),
+            scope_stack: Vec::new(),
+        }
+    }
+
+    /// Creates a builder that automatically indents the contents of the given
+    /// node kinds.
+    ///
+    /// Each `scope` kind becomes an "indent scope": `start_node` for a scoped
+    /// kind pushes an `Indent` of `unit` as the node's first child, and the
+    /// matching `finish_node` emits the balancing `Dedent`. This removes the
+    /// manual `indent`/`dedent` bookkeeping a parser would otherwise need, while
+    /// still interleaving correctly with explicit `newline` calls so that
+    /// `extract_text` round-trips to the original source.
+    pub fn with_indent_scopes(scopes: impl IntoIterator<Item = S>, unit: impl Into<String>) -> Self {
+        Self {
+            inner: GreenNodeBuilder::new(),
+            indent_scopes: scopes.into_iter().map(|s| s.into_raw().0).collect(),
+            indent_unit: unit.into(),
+            scope_stack: Vec::new(),
         }
     }
 
@@ -43,6 +71,9 @@ impl<'cache, 'interner, S: Syntax, I: Interner> Builder<'cache, 'interner, S, I>
     pub fn with_cache(cache: &'cache mut NodeCache<'interner, I>) -> Self {
         Self {
             inner: GreenNodeBuilder::with_cache(cache),
+            indent_scopes: Vec::new(),
+            indent_unit: String::new(),
+            scope_stack: Vec::new(),
         }
     }
 
@@ -50,18 +81,34 @@ impl<'cache, 'interner, S: Syntax, I: Interner> Builder<'cache, 'interner, S, I>
     pub fn with_interner(interner: &'interner mut I) -> Self {
         Self {
             inner: GreenNodeBuilder::with_interner(interner),
+            indent_scopes: Vec::new(),
+            indent_unit: String::new(),
+            scope_stack: Vec::new(),
         }
     }
 
     /// Starts a new node with the given inner syntax kind.
     ///
     /// This is a convenience method equivalent to `start_node(Syntax2D::Token(kind))`.
+    /// If `kind` is a registered indent scope, an `Indent` token is emitted as
+    /// the node's first child (see [`Builder::with_indent_scopes`]).
     pub fn start_node(&mut self, kind: S) {
         self.inner.start_node(Syntax2D::Token(kind));
+        let scoped = self.indent_scopes.contains(&kind.into_raw().0);
+        if scoped {
+            self.inner.token(Syntax2D::Indent, &self.indent_unit);
+        }
+        self.scope_stack.push(scoped);
     }
 
     /// Finishes the current node.
+    ///
+    /// If the node was opened as an indent scope, the balancing `Dedent` token
+    /// is emitted before the node is closed.
     pub fn finish_node(&mut self) {
+        if self.scope_stack.pop() == Some(true) {
+            self.inner.static_token(Syntax2D::Dedent);
+        }
         self.inner.finish_node();
     }
 
@@ -0,0 +1,202 @@
+//! Indentation-style detection and whole-tree reindentation.
+
+use crate::{
+    edit::{path_from_root, root_green},
+    green::Builder,
+    red::SyntaxNode2D,
+    syntax::Syntax2D,
+};
+use cstree::{
+    RawSyntaxKind, Syntax,
+    green::GreenNode,
+    interning::Resolver,
+    util::NodeOrToken,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/**************************************************************/
+
+/// Reports how deeply `node` is nested, as the net number of live `Indent`
+/// tokens on the path from the root down to it.
+///
+/// Unlike rust-analyzer's `IndentLevel::from_node`, which re-scans preceding
+/// whitespace, this reads the depth directly from the live indent stack encoded
+/// in the tree.
+pub fn indent_level<S: Syntax>(node: &SyntaxNode2D<S>) -> usize {
+    active_indents::<S>(node).len()
+}
+
+/// The concatenated indentation string that precedes `node` on its line,
+/// i.e. the active indent stack joined outermost-first.
+pub fn indent_prefix<S: Syntax>(node: &SyntaxNode2D<S>) -> String {
+    active_indents::<S>(node).concat()
+}
+
+/// Collects the indentation strings live when the pre-order walk reaches
+/// `node`, outermost first.
+fn active_indents<S: Syntax>(node: &SyntaxNode2D<S>) -> Vec<String> {
+    let resolver = match node.inner().resolver() {
+        Some(resolver) => resolver,
+        None => return Vec::new(),
+    };
+    let path = path_from_root(node.inner());
+    let mut stack = Vec::new();
+    walk_stack::<S, _>(root_green(node.inner()), &path, 0, &**resolver, &mut stack);
+    stack
+}
+
+/// Follows `path` down to its node, tracking the indent strings of `Indent`/
+/// `Dedent` tokens that are direct children of each ancestor and precede the
+/// path. The contents of preceding sibling nodes are not walked.
+fn walk_stack<S: Syntax, R: Resolver + ?Sized>(
+    node: &GreenNode,
+    path: &[usize],
+    depth: usize,
+    resolver: &R,
+    stack: &mut Vec<String>,
+) -> bool {
+    for (i, child) in node.children().enumerate() {
+        if depth < path.len() && i == path[depth] {
+            if depth + 1 == path.len() {
+                return true;
+            }
+            if let NodeOrToken::Node(n) = child {
+                if walk_stack::<S, R>(n, path, depth + 1, resolver, stack) {
+                    return true;
+                }
+            }
+        } else if let NodeOrToken::Token(t) = child {
+            match Syntax2D::<S>::from_raw(t.kind()) {
+                Syntax2D::Indent => stack.push(t.text(resolver).unwrap_or("").to_string()),
+                Syntax2D::Dedent => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+/// The indentation style of a document: tabs, or a fixed number of spaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndentStyle {
+    /// One hard tab per level.
+    Tabs,
+    /// `n` spaces per level.
+    Spaces(u8),
+}
+
+impl IndentStyle {
+    /// Infers a style from a single indentation string: a leading tab means
+    /// [`IndentStyle::Tabs`], otherwise [`IndentStyle::Spaces`] of its width.
+    ///
+    /// Named `from_indent_str` rather than `from_str` so it does not shadow the
+    /// fallible [`std::str::FromStr`] convention.
+    pub fn from_indent_str(s: &str) -> IndentStyle {
+        if s.starts_with('\t') {
+            IndentStyle::Tabs
+        } else {
+            IndentStyle::Spaces(s.chars().count() as u8)
+        }
+    }
+
+    /// The canonical indentation string for one level of this style. `Tabs`
+    /// borrows a static string; `Spaces` owns its run of spaces.
+    pub fn as_str(&self) -> Cow<'static, str> {
+        match self {
+            IndentStyle::Tabs => Cow::Borrowed("\t"),
+            IndentStyle::Spaces(n) => Cow::Owned(" ".repeat(*n as usize)),
+        }
+    }
+}
+
+impl Default for IndentStyle {
+    fn default() -> Self {
+        IndentStyle::Spaces(4)
+    }
+}
+
+/// Detects the dominant indentation style of a tree by a majority vote over the
+/// strings attached to its `Indent` tokens.
+///
+/// Ties are broken deterministically (see below) and empty documents fall back
+/// to [`IndentStyle::default`].
+pub fn detect_indent_style<S: Syntax>(root: &SyntaxNode2D<S>) -> IndentStyle {
+    let resolver = match root.inner().resolver() {
+        Some(resolver) => resolver,
+        None => return IndentStyle::default(),
+    };
+
+    let mut votes: HashMap<IndentStyle, usize> = HashMap::new();
+    collect_styles::<S, _>(root.green(), &**resolver, &mut votes);
+
+    // `max_by_key` over a `HashMap` picks an arbitrary winner on ties, so break
+    // ties deterministically: most votes first, then by a fixed style order
+    // (tabs before spaces, narrower spacing before wider).
+    let mut tally: Vec<(IndentStyle, usize)> = votes.into_iter().collect();
+    tally.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| style_rank(a.0).cmp(&style_rank(b.0))));
+    tally.first().map(|&(style, _)| style).unwrap_or_default()
+}
+
+/// A total order over styles used only to break vote ties deterministically.
+fn style_rank(style: IndentStyle) -> (u8, u8) {
+    match style {
+        IndentStyle::Tabs => (0, 0),
+        IndentStyle::Spaces(n) => (1, n),
+    }
+}
+
+/// Tallies an [`IndentStyle`] vote for every `Indent` token in the tree.
+fn collect_styles<S: Syntax, R: Resolver + ?Sized>(
+    node: &GreenNode,
+    resolver: &R,
+    votes: &mut HashMap<IndentStyle, usize>,
+) {
+    for child in node.children() {
+        match child {
+            NodeOrToken::Token(token) => {
+                if let Syntax2D::Indent = Syntax2D::<S>::from_raw(token.kind()) {
+                    let text = token.text(resolver).unwrap_or("");
+                    *votes.entry(IndentStyle::from_indent_str(text)).or_insert(0) += 1;
+                }
+            }
+            NodeOrToken::Node(child_node) => collect_styles::<S, R>(child_node, resolver, votes),
+        }
+    }
+}
+
+/// Rebuilds `root` into `builder`, replacing every `Indent` token's string with
+/// the canonical string for `style` and leaving `Token` text and `Newline`
+/// tokens untouched.
+pub fn reindent<S: Syntax>(builder: &mut Builder<S>, root: &SyntaxNode2D<S>, style: IndentStyle) {
+    let resolver = root
+        .inner()
+        .resolver()
+        .expect("reindent requires a resolver-backed tree");
+    let unit = style.as_str();
+    replay::<S, _>(builder, root.green(), &**resolver, &unit);
+}
+
+/// Re-emits the tree through the builder, canonicalizing `Indent` strings.
+fn replay<S: Syntax, R: Resolver + ?Sized>(
+    builder: &mut Builder<S>,
+    node: &GreenNode,
+    resolver: &R,
+    unit: &str,
+) {
+    builder.start_node(S::from_raw(RawSyntaxKind(node.kind().0)));
+    for child in node.children() {
+        match child {
+            NodeOrToken::Token(token) => match Syntax2D::<S>::from_raw(token.kind()) {
+                Syntax2D::Indent => builder.indent(unit),
+                Syntax2D::Dedent => builder.dedent(),
+                Syntax2D::Newline => builder.newline(),
+                Syntax2D::Token(s) => builder.token(s, token.text(resolver).unwrap_or("")),
+            },
+            NodeOrToken::Node(child_node) => replay::<S, R>(builder, child_node, resolver, unit),
+        }
+    }
+    builder.finish_node();
+}
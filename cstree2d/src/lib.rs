@@ -4,14 +4,22 @@
 //! syntax trees, particularly useful for indentation-sensitive languages.
 
 pub use cstree;
+pub use green::Builder;
+pub use extract_text_to_string as extract_text;
 
+pub mod diff;
+pub mod edit;
 pub mod green;
+pub mod indent;
 pub mod red;
+#[cfg(feature = "serde")]
+mod serde_impls;
 pub mod syntax;
+pub mod ted;
+pub mod text;
 
 /**************************************************************/
 
-use crate::green::extract_text;
 use cstree::{
     Syntax,
     green::GreenNode,
@@ -53,6 +61,6 @@ struct TextDisplay<'a, S, I: ?Sized> {
 
 impl<S: Syntax, I: Resolver + ?Sized> Display for TextDisplay<'_, S, I> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        extract_text::<S, I>(self.node, self.resolver, f)
+        green::extract_text::<S, I>(self.node, self.resolver, f)
     }
 }
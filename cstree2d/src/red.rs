@@ -1,8 +1,10 @@
 use crate::{green::extract_text, syntax::Syntax2D};
 use cstree::{
     Syntax,
-    green::GreenNode,
+    green::{GreenNode, GreenToken},
+    interning::Resolver,
     syntax::{ResolvedNode, SyntaxNode},
+    util::NodeOrToken,
 };
 use std::fmt::{Display, Formatter};
 
@@ -31,6 +33,88 @@ impl<S: Syntax> ResolvedNode2D<S> {
     pub fn green(&self) -> &GreenNode {
         self.inner.green()
     }
+
+    /// Iterates over the `Token` leaves of this tree, pairing each with the
+    /// indentation that would be emitted before it.
+    ///
+    /// This drives the same stack machine as [`extract_text`]: it pushes on
+    /// `Indent`, pops on `Dedent`, and marks the line-start flag after each
+    /// `Newline`. For every `Token` it yields the token together with a snapshot
+    /// of the active indentation strings and whether the token begins a line.
+    ///
+    /// [`extract_text`]: crate::green::extract_text
+    pub fn tokens_with_indent(&self) -> std::vec::IntoIter<TokenWithIndent<'_>> {
+        let resolver = self.inner.resolver();
+        let mut out = Vec::new();
+        let mut stack: Vec<&str> = Vec::new();
+        let mut at_line_start = false;
+        collect_tokens_with_indent::<S, _>(
+            self.green(),
+            &**resolver,
+            &mut stack,
+            &mut at_line_start,
+            &mut out,
+        );
+        out.into_iter()
+    }
+}
+
+/// A `Token` leaf paired with the indentation active when it is rendered.
+#[derive(Debug, Clone)]
+pub struct TokenWithIndent<'a> {
+    /// The green token.
+    pub token: GreenToken,
+    /// Snapshot of the active indentation stack, outermost first.
+    pub indent: Vec<&'a str>,
+    /// Whether a `Newline` or an `Indent` precedes this token with no other
+    /// `Token` in between. The first token of the tree is not marked unless an
+    /// `Indent` precedes it.
+    pub at_line_start: bool,
+}
+
+impl<'a> TokenWithIndent<'a> {
+    /// The active indentation strings, outermost first.
+    pub fn indent(&self) -> &[&'a str] {
+        &self.indent
+    }
+}
+
+/// Walks `node` in pre-order, driving the indentation stack machine and pushing
+/// a [`TokenWithIndent`] for every `Token` leaf encountered.
+fn collect_tokens_with_indent<'a, S: Syntax, R: Resolver + ?Sized>(
+    node: &'a GreenNode,
+    resolver: &'a R,
+    stack: &mut Vec<&'a str>,
+    at_line_start: &mut bool,
+    out: &mut Vec<TokenWithIndent<'a>>,
+) {
+    for child in node.children() {
+        match child {
+            NodeOrToken::Token(token) => match Syntax2D::<S>::from_raw(token.kind()) {
+                Syntax2D::Indent => {
+                    stack.push(token.text(resolver).unwrap());
+                    *at_line_start = true;
+                }
+                Syntax2D::Dedent => {
+                    stack.pop();
+                }
+                Syntax2D::Newline => {
+                    *at_line_start = true;
+                }
+                Syntax2D::Token(_) => {
+                    out.push(TokenWithIndent {
+                        token: token.clone(),
+                        indent: stack.clone(),
+                        at_line_start: *at_line_start,
+                    });
+                    *at_line_start = false;
+                }
+            },
+            NodeOrToken::Node(child_node) => {
+                collect_tokens_with_indent::<S, R>(child_node, resolver, stack, at_line_start, out);
+            }
+        }
+    }
 }
 
 impl<S: Syntax> Display for ResolvedNode2D<S> {
@@ -0,0 +1,94 @@
+use crate::{green::Builder, red::ResolvedNode2D, syntax::Syntax2D};
+use cstree::{
+    RawSyntaxKind, Syntax,
+    green::GreenNode,
+    interning::Resolver,
+    util::NodeOrToken,
+};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::Error as _,
+};
+
+/**************************************************************/
+
+/// A single event in the flattened representation of a `Syntax2D` green tree.
+///
+/// The stream is replayed through [`Builder`]'s `start_node`/`finish_node`/
+/// `token`/`indent`/`dedent`/`newline` methods on deserialization, so the
+/// reconstructed tree shares the same interner-backed structure. The raw `u32`
+/// carried by `StartNode`/`Token` is the inner `S` kind; the niche-optimized
+/// `Syntax2D` sentinels are recovered from the dedicated variants.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Event {
+    /// Opens a node of the given inner `S` kind.
+    StartNode(u32),
+    /// Closes the most recently opened node.
+    FinishNode,
+    /// A `Token(S)` leaf: inner `S` kind and its interned text.
+    Token(u32, String),
+    /// An `Indent` leaf carrying its indentation string.
+    Indent(String),
+    /// A `Dedent` leaf.
+    Dedent,
+    /// A `Newline` leaf.
+    Newline,
+}
+
+/// Walks `node` in pre-order, emitting one [`Event`] per node boundary and leaf.
+fn collect_events<S: Syntax, R: Resolver + ?Sized>(
+    node: &GreenNode,
+    resolver: &R,
+    out: &mut Vec<Event>,
+) {
+    out.push(Event::StartNode(node.kind().0));
+    for child in node.children() {
+        match child {
+            NodeOrToken::Token(token) => match Syntax2D::<S>::from_raw(token.kind()) {
+                Syntax2D::Indent => {
+                    out.push(Event::Indent(token.text(resolver).unwrap().to_string()));
+                }
+                Syntax2D::Dedent => out.push(Event::Dedent),
+                Syntax2D::Newline => out.push(Event::Newline),
+                Syntax2D::Token(s) => {
+                    out.push(Event::Token(s.into_raw().0, token.text(resolver).unwrap().to_string()));
+                }
+            },
+            NodeOrToken::Node(child_node) => collect_events::<S, R>(child_node, resolver, out),
+        }
+    }
+    out.push(Event::FinishNode);
+}
+
+/**************************************************************/
+
+impl<S: Syntax> Serialize for ResolvedNode2D<S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        let resolver = self.inner().resolver();
+        let mut events = Vec::new();
+        collect_events::<S, _>(self.green(), &**resolver, &mut events);
+        events.serialize(serializer)
+    }
+}
+
+impl<'de, S: Syntax> Deserialize<'de> for ResolvedNode2D<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let events = Vec::<Event>::deserialize(deserializer)?;
+        let mut builder: Builder<S> = Builder::new();
+        for event in &events {
+            match event {
+                Event::StartNode(raw) => builder.start_node(S::from_raw(RawSyntaxKind(*raw))),
+                Event::FinishNode => builder.finish_node(),
+                Event::Token(raw, text) => builder.token(S::from_raw(RawSyntaxKind(*raw)), text),
+                Event::Indent(text) => builder.indent(text),
+                Event::Dedent => builder.dedent(),
+                Event::Newline => builder.newline(),
+            }
+        }
+        // `red()` requires exactly one root node to have been closed.
+        if events.is_empty() {
+            return Err(D::Error::custom("empty Syntax2D tree"));
+        }
+        Ok(builder.red())
+    }
+}
@@ -0,0 +1,138 @@
+//! Indentation-aware tree editing on the red tree, modeled on rust-analyzer's
+//! `ted`. `insert_children`/`replace_children` splice subtrees into a new
+//! location and automatically re-emit the `Indent`/`Dedent` tokens needed for
+//! the block to adopt the surrounding indentation depth, so a `Text` block
+//! moved into a nested node renders with the right leading whitespace without
+//! the caller rebalancing the indent stack by hand.
+
+use crate::{
+    edit::{
+        InsertPosition, SharedResolver, clone_element, collect_indent_stack, find_dedent,
+        leading_indents, path_from_root, rebuild_spine, root_green,
+    },
+    red::ResolvedNode2D,
+    syntax::Syntax2D,
+};
+use cstree::{
+    Syntax,
+    green::{GreenNode, GreenToken},
+    syntax::{ResolvedNode, SyntaxNode},
+    util::NodeOrToken,
+};
+use std::ops::Range;
+
+/**************************************************************/
+
+/// Inserts `elements` among `parent`'s children at `position`, re-homing each
+/// to `parent`'s indentation depth, and returns the rebuilt tree.
+pub fn insert_children<S: Syntax>(
+    parent: &ResolvedNode2D<S>,
+    position: InsertPosition,
+    elements: impl IntoIterator<Item = GreenNode>,
+) -> ResolvedNode2D<S> {
+    let len = parent.green().children().count();
+    let at = position.resolve(len);
+    splice(parent, at..at, elements)
+}
+
+/// Replaces `parent`'s children in `range` with `elements`, re-homing each to
+/// `parent`'s indentation depth, and returns the rebuilt tree.
+pub fn replace_children<S: Syntax>(
+    parent: &ResolvedNode2D<S>,
+    range: Range<usize>,
+    elements: impl IntoIterator<Item = GreenNode>,
+) -> ResolvedNode2D<S> {
+    splice(parent, range, elements)
+}
+
+/// Shared splice: computes the indentation delta between `parent` and each
+/// inserted subtree, prepends/appends the delta as `Indent`/`Dedent` tokens,
+/// then rebuilds the spine up to the root.
+fn splice<S: Syntax>(
+    parent: &ResolvedNode2D<S>,
+    range: Range<usize>,
+    elements: impl IntoIterator<Item = GreenNode>,
+) -> ResolvedNode2D<S> {
+    let path = path_from_root(parent.inner());
+    let root = root_green(parent.inner());
+
+    // The live `Indent` depth at the insertion point, and the nearest enclosing
+    // indent string to reuse when we need to add indentation.
+    let target_depth = live_indent_depth(parent.inner());
+    let mut enclosing = Vec::new();
+    collect_indent_stack(root, &path, 0, &mut enclosing);
+    let unit = enclosing.last().cloned();
+    let dedent = find_dedent(root);
+
+    let mut children: Vec<NodeOrToken<GreenNode, GreenToken>> =
+        parent.green().children().map(clone_element).collect();
+
+    let mut spliced = Vec::new();
+    for subtree in elements {
+        spliced.push(rehome(subtree, target_depth, unit.as_ref(), dedent.as_ref()));
+    }
+    children.splice(range, spliced);
+
+    let new_parent = GreenNode::new(parent.green().kind(), children);
+    let new_root = rebuild_spine(root, &path, new_parent);
+    // Carry the resolver through so `extract_text`/`Display` keep working on the
+    // rebuilt tree; `new_root` alone would leave the result unresolvable.
+    ResolvedNode2D::new(ResolvedNode::new_root_with_resolver(
+        new_root,
+        SharedResolver::of(parent),
+    ))
+}
+
+/// Wraps `subtree` with the `Indent`/`Dedent` delta that re-homes it from its
+/// own base depth to `target_depth`.
+fn rehome(
+    subtree: GreenNode,
+    target_depth: usize,
+    unit: Option<&GreenToken>,
+    dedent: Option<&GreenToken>,
+) -> NodeOrToken<GreenNode, GreenToken> {
+    let base = leading_indents(&subtree);
+    if target_depth == base {
+        return NodeOrToken::Node(subtree);
+    }
+    if target_depth > base {
+        // Deeper destination: wrap the block in the extra enclosing `Indent`s
+        // (balanced by `Dedent`s) so its inner `Newline`s render further in.
+        let mut elements = Vec::new();
+        if let Some(unit) = unit {
+            for _ in 0..(target_depth - base) {
+                elements.push(NodeOrToken::Token(unit.clone()));
+            }
+        }
+        elements.push(NodeOrToken::Node(subtree.clone()));
+        if let Some(dedent) = dedent {
+            for _ in 0..(target_depth - base) {
+                elements.push(NodeOrToken::Token(dedent.clone()));
+            }
+        }
+        NodeOrToken::Node(GreenNode::new(subtree.kind(), elements))
+    } else {
+        // Shallower destination: drop the block's own surplus leading `Indent`s
+        // and the matching trailing `Dedent`s from inside the block. Wrapping it
+        // in `Dedent`s instead would pop the surrounding context's indentation
+        // before the block rendered, corrupting the siblings that follow.
+        let surplus = base - target_depth;
+        let mut children: Vec<NodeOrToken<GreenNode, GreenToken>> =
+            subtree.children().map(clone_element).collect();
+        children.drain(0..surplus);
+        for _ in 0..surplus {
+            if matches!(children.last(), Some(NodeOrToken::Token(t)) if t.kind().0 == u32::MAX - 1) {
+                children.pop();
+            }
+        }
+        NodeOrToken::Node(GreenNode::new(subtree.kind(), children))
+    }
+}
+
+/// The net live `Indent` depth on the path from the root down to `node`.
+fn live_indent_depth<S: Syntax>(node: &SyntaxNode<Syntax2D<S>>) -> usize {
+    let path = path_from_root(node);
+    let mut stack = Vec::new();
+    collect_indent_stack(root_green(node), &path, 0, &mut stack);
+    stack.len()
+}
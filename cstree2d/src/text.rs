@@ -0,0 +1,191 @@
+use crate::{red::ResolvedNode2D, syntax::Syntax2D};
+use cstree::{
+    Syntax,
+    green::{GreenNode, GreenToken},
+    interning::Resolver,
+    util::NodeOrToken,
+};
+
+/**************************************************************/
+
+/// One contiguous run of rendered characters, remembering whether it came from
+/// a `Token` leaf (and which one) or from re-emitted indentation / newlines.
+struct Piece<'a> {
+    /// The rendered text of this run.
+    text: &'a str,
+    /// The source token, if this run is a `Token` leaf's text.
+    token: Option<GreenToken>,
+    /// The number of bytes before this run in the rendered output.
+    start: usize,
+}
+
+/// A lazy view over the indentation-expanded text of a [`ResolvedNode2D`].
+///
+/// The rendered length differs from the concatenated token text because
+/// `Indent`/`Dedent` contribute nothing inline yet their strings are re-emitted
+/// after every `Newline`. `SyntaxText2D` records the run boundaries once so it
+/// can answer `len`, `char_at`, slicing, and bidirectional offset/token queries
+/// without materializing the whole string.
+pub struct SyntaxText2D<'a> {
+    pieces: Vec<Piece<'a>>,
+    len: usize,
+}
+
+impl<'a> SyntaxText2D<'a> {
+    /// Builds the view by driving the same stack machine as `extract_text`.
+    fn build<S: Syntax, R: Resolver + ?Sized>(node: &'a GreenNode, resolver: &'a R) -> Self {
+        let mut pieces = Vec::new();
+        let mut len = 0;
+        let mut stack: Vec<&str> = Vec::new();
+        let mut pending = false;
+
+        fn push<'a>(pieces: &mut Vec<Piece<'a>>, len: &mut usize, text: &'a str, token: Option<GreenToken>) {
+            if text.is_empty() {
+                return;
+            }
+            pieces.push(Piece { text, token, start: *len });
+            *len += text.len();
+        }
+
+        fn walk<'a, S: Syntax, R: Resolver + ?Sized>(
+            node: &'a GreenNode,
+            resolver: &'a R,
+            pieces: &mut Vec<Piece<'a>>,
+            len: &mut usize,
+            stack: &mut Vec<&'a str>,
+            pending: &mut bool,
+        ) {
+            for child in node.children() {
+                match child {
+                    NodeOrToken::Token(token) => match Syntax2D::<S>::from_raw(token.kind()) {
+                        Syntax2D::Indent => {
+                            stack.push(token.text(resolver).unwrap());
+                            *pending = true;
+                        }
+                        Syntax2D::Dedent => {
+                            stack.pop();
+                        }
+                        Syntax2D::Newline => {
+                            if *pending {
+                                for indent in stack.iter() {
+                                    push(pieces, len, indent, None);
+                                }
+                            }
+                            push(pieces, len, "\n", None);
+                            *pending = !stack.is_empty();
+                        }
+                        Syntax2D::Token(_) => {
+                            if *pending {
+                                for indent in stack.iter() {
+                                    push(pieces, len, indent, None);
+                                }
+                                *pending = false;
+                            }
+                            push(pieces, len, token.text(resolver).unwrap(), Some(token.clone()));
+                        }
+                    },
+                    NodeOrToken::Node(child_node) => {
+                        walk::<S, R>(child_node, resolver, pieces, len, stack, pending);
+                    }
+                }
+            }
+        }
+
+        walk::<S, R>(node, resolver, &mut pieces, &mut len, &mut stack, &mut pending);
+        SyntaxText2D { pieces, len }
+    }
+
+    /// The length of the rendered text, in bytes.
+    ///
+    /// Offsets here are byte offsets, matching the `TextRange`s produced by
+    /// [`crate::diff`], so the two APIs compose.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the rendered text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The character whose byte range contains the given rendered offset, if the
+    /// offset is in bounds and on a `char` boundary.
+    pub fn char_at(&self, offset: usize) -> Option<char> {
+        let piece = self.piece_at(offset)?;
+        piece.text.get(offset - piece.start..).and_then(|s| s.chars().next())
+    }
+
+    /// Materializes the text in the given rendered byte range.
+    pub fn slice(&self, range: std::ops::Range<usize>) -> String {
+        let start = range.start.min(self.len);
+        let end = range.end.min(self.len);
+        let mut out = String::new();
+        if start >= end {
+            return out;
+        }
+        let mut idx = match self.piece_index_at(start) {
+            Some(idx) => idx,
+            None => return out,
+        };
+        while idx < self.pieces.len() {
+            let piece = &self.pieces[idx];
+            if piece.start >= end {
+                break;
+            }
+            let from = start.saturating_sub(piece.start).min(piece.text.len());
+            let to = (end - piece.start).min(piece.text.len());
+            if let Some(s) = piece.text.get(from..to) {
+                out.push_str(s);
+            }
+            idx += 1;
+        }
+        out
+    }
+
+    /// Maps a rendered offset back to the token it falls in (or the next token,
+    /// for an offset inside re-emitted indentation), with the offset within
+    /// that token's text.
+    pub fn offset_to_token(&self, offset: usize) -> Option<(GreenToken, usize)> {
+        let idx = self.piece_index_at(offset)?;
+        let piece = &self.pieces[idx];
+        if let Some(token) = &piece.token {
+            return Some((token.clone(), offset - piece.start));
+        }
+        // Inside indentation or a newline: snap forward to the next token.
+        self.pieces[idx..]
+            .iter()
+            .find_map(|p| p.token.clone().map(|t| (t, 0)))
+    }
+
+    /// The rendered offset at which `token`'s text begins.
+    pub fn token_start_offset(&self, token: &GreenToken) -> Option<usize> {
+        self.pieces
+            .iter()
+            .find(|p| p.token.as_ref() == Some(token))
+            .map(|p| p.start)
+    }
+
+    fn piece_index_at(&self, offset: usize) -> Option<usize> {
+        if offset >= self.len {
+            return None;
+        }
+        // The pieces are contiguous and start-sorted, so the piece containing
+        // `offset` is the last one whose start is `<= offset`.
+        let after = self.pieces.partition_point(|p| p.start <= offset);
+        let idx = after.checked_sub(1)?;
+        let piece = &self.pieces[idx];
+        (offset < piece.start + piece.text.len()).then_some(idx)
+    }
+
+    fn piece_at(&self, offset: usize) -> Option<&Piece<'a>> {
+        self.piece_index_at(offset).map(|i| &self.pieces[i])
+    }
+}
+
+impl<S: Syntax> ResolvedNode2D<S> {
+    /// Returns a lazy [`SyntaxText2D`] view over this node's rendered text.
+    pub fn text(&self) -> SyntaxText2D<'_> {
+        let resolver = self.inner().resolver();
+        SyntaxText2D::build::<S, _>(self.green(), &**resolver)
+    }
+}
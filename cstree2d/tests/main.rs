@@ -1,6 +1,25 @@
-use cstree2d::{Builder, cstree::Syntax, extract_text, syntax::Syntax2D};
+use cstree2d::{
+    Builder,
+    cstree::{
+        Syntax,
+        text::{TextRange, TextSize},
+    },
+    diff::diff,
+    edit::{IndentLevel, InsertPosition},
+    extract_text,
+    indent::{IndentStyle, detect_indent_style, indent_level, indent_prefix, reindent},
+    red::SyntaxNode2D,
+    syntax::Syntax2D,
+    ted,
+};
 use indoc::indoc;
 
+/// Builds a resolver-backed [`SyntaxNode2D`] for the APIs that take one.
+fn to_syntax(builder: Builder<TestSyntax>) -> SyntaxNode2D<TestSyntax> {
+    let resolved = builder.red();
+    SyntaxNode2D::new((**resolved.inner()).clone())
+}
+
 /**************************************************************/
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Syntax)]
@@ -185,3 +204,322 @@ fn test_dump_text_mixed_indentation_styles() {
         }
     );
 }
+
+/**************************************************************/
+
+#[test]
+fn test_edit_insert_and_replace_child_is_immutable() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "a");
+    builder.finish_node();
+    let tree = builder.red();
+
+    let mut snippet_builder: Builder<TestSyntax> = Builder::new();
+    snippet_builder.start_node(TestSyntax::Text);
+    snippet_builder.token(TestSyntax::Text, "b");
+    snippet_builder.finish_node();
+    let snippet = snippet_builder.red();
+
+    // Inserting returns a fresh tree and leaves the original untouched.
+    let inserted = tree.insert_child(InsertPosition::Last, &snippet);
+    assert_eq!(tree.to_string(), "a");
+    assert_eq!(inserted.to_string(), "ab");
+
+    // Replacing swaps the child out in the returned tree.
+    let replaced = tree.replace_child(0, &snippet);
+    assert_eq!(tree.to_string(), "a");
+    assert_eq!(replaced.to_string(), "b");
+}
+
+/**************************************************************/
+
+#[test]
+fn test_indent_level_display_and_reindent_to() {
+    assert_eq!(format!("{}", IndentLevel(2)), "        ");
+
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.indent("  ");
+    builder.token(TestSyntax::Text, "x");
+    builder.newline();
+    builder.token(TestSyntax::Text, "y");
+    builder.dedent();
+    builder.finish_node();
+    let tree = builder.red();
+
+    assert_eq!(tree.to_string(), "  x\n  y");
+    assert_eq!(IndentLevel::of(&tree), IndentLevel(0));
+
+    // Flattening to level 0 strips the leading indentation stack.
+    assert_eq!(tree.reindent_to(IndentLevel(0)).to_string(), "x\ny");
+    // Re-homing to the original level is a round-trip.
+    assert_eq!(tree.reindent_to(IndentLevel(1)).to_string(), "  x\n  y");
+}
+
+/**************************************************************/
+
+#[test]
+fn test_tokens_with_indent_snapshots_stack() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "a");
+    builder.newline();
+    builder.indent("  ");
+    builder.token(TestSyntax::Text, "b");
+    builder.dedent();
+    builder.finish_node();
+    let tree = builder.red();
+
+    let tokens: Vec<_> = tree.tokens_with_indent().collect();
+    assert_eq!(tokens.len(), 2);
+
+    // The first token opens the document at column zero.
+    assert!(tokens[0].indent().is_empty());
+    assert!(!tokens[0].at_line_start);
+
+    // The second token sits under the active indent and starts its line.
+    assert_eq!(tokens[1].indent(), ["  "]);
+    assert!(tokens[1].at_line_start);
+}
+
+/**************************************************************/
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip_preserves_rendered_text() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "line1");
+    builder.newline();
+    builder.indent("    ");
+    builder.token(TestSyntax::Text, "indented");
+    builder.dedent();
+    builder.finish_node();
+    let tree = builder.red();
+
+    let json = serde_json::to_string(&tree).expect("serialize");
+    let restored: cstree2d::red::ResolvedNode2D<TestSyntax> =
+        serde_json::from_str(&json).expect("deserialize");
+
+    assert_eq!(restored.to_string(), tree.to_string());
+}
+
+/**************************************************************/
+
+#[test]
+fn test_syntax_text_offsets_and_mapping() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "a");
+    builder.newline();
+    builder.indent("  ");
+    builder.token(TestSyntax::Text, "b");
+    builder.dedent();
+    builder.finish_node();
+    let tree = builder.red();
+
+    let text = tree.text();
+    // Rendered as "a\n  b": five bytes once the indentation is expanded.
+    assert_eq!(text.len(), 5);
+    assert_eq!(text.char_at(0), Some('a'));
+    assert_eq!(text.char_at(4), Some('b'));
+    assert_eq!(text.slice(0..1), "a");
+    assert_eq!(text.slice(2..5), "  b");
+
+    // The 'b' at offset 4 maps back to its token, and the mapping is invertible.
+    let (token, intra) = text.offset_to_token(4).expect("token at offset 4");
+    assert_eq!(intra, 0);
+    assert_eq!(text.token_start_offset(&token), Some(4));
+}
+
+/**************************************************************/
+
+#[test]
+fn test_ted_insert_children_keeps_resolver() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "a");
+    builder.finish_node();
+    let tree = builder.red();
+
+    let mut snippet_builder: Builder<TestSyntax> = Builder::new();
+    snippet_builder.start_node(TestSyntax::Text);
+    snippet_builder.token(TestSyntax::Text, "b");
+    snippet_builder.finish_node();
+    let snippet = snippet_builder.red();
+
+    // The rebuilt tree must still render, i.e. keep its resolver.
+    let inserted = ted::insert_children(&tree, InsertPosition::Last, [snippet.green().clone()]);
+    assert_eq!(tree.to_string(), "a");
+    assert_eq!(inserted.to_string(), "ab");
+
+    let replaced = ted::replace_children(&tree, 0..1, [snippet.green().clone()]);
+    assert_eq!(replaced.to_string(), "b");
+}
+
+/**************************************************************/
+
+#[test]
+fn test_diff_reports_token_text_change() {
+    let mut old_builder: Builder<TestSyntax> = Builder::new();
+    old_builder.start_node(TestSyntax::Root);
+    old_builder.token(TestSyntax::Text, "cat");
+    old_builder.finish_node();
+    let old = to_syntax(old_builder);
+
+    let mut new_builder: Builder<TestSyntax> = Builder::new();
+    new_builder.start_node(TestSyntax::Root);
+    new_builder.token(TestSyntax::Text, "dog");
+    new_builder.finish_node();
+    let new = to_syntax(new_builder);
+
+    // A same-kind, same-length token whose text changed must not be dropped.
+    let edits = diff(&old, &new);
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0].range, TextRange::new(TextSize::new(0), TextSize::new(3)));
+    assert_eq!(edits[0].insert, "dog");
+
+    // An unchanged tree produces no edits.
+    assert!(diff(&old, &old).is_empty());
+}
+
+/**************************************************************/
+
+#[test]
+fn test_indent_style_detect_and_reindent() {
+    assert_eq!(IndentStyle::from_indent_str("\t"), IndentStyle::Tabs);
+    assert_eq!(IndentStyle::from_indent_str("    "), IndentStyle::Spaces(4));
+    assert_eq!(IndentStyle::Spaces(2).as_str().as_ref(), "  ");
+
+    // A tree indented with four spaces detects as Spaces(4).
+    let mut detect_builder: Builder<TestSyntax> = Builder::new();
+    detect_builder.start_node(TestSyntax::Root);
+    detect_builder.indent("    ");
+    detect_builder.token(TestSyntax::Text, "a");
+    detect_builder.dedent();
+    detect_builder.finish_node();
+    let detected = to_syntax(detect_builder);
+    assert_eq!(detect_indent_style(&detected), IndentStyle::Spaces(4));
+
+    // Normalizing the mixed-style document collapses both indents to two spaces.
+    let mut mixed_builder: Builder<TestSyntax> = Builder::new();
+    mixed_builder.start_node(TestSyntax::Root);
+    mixed_builder.token(TestSyntax::Text, "start");
+    mixed_builder.newline();
+    mixed_builder.indent("    ");
+    mixed_builder.indent("# ");
+    mixed_builder.token(TestSyntax::Text, "comment");
+    mixed_builder.dedent();
+    mixed_builder.dedent();
+    mixed_builder.finish_node();
+    let mixed = to_syntax(mixed_builder);
+
+    let mut out_builder: Builder<TestSyntax> = Builder::new();
+    reindent(&mut out_builder, &mixed, IndentStyle::Spaces(2));
+    assert_eq!(out_builder.red().to_string(), "start\n    comment");
+}
+
+/**************************************************************/
+
+#[test]
+fn test_indent_level_and_prefix_of_nested_node() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "top");
+    builder.newline();
+    builder.indent("  ");
+    builder.start_node(TestSyntax::Text);
+    builder.token(TestSyntax::Text, "b");
+    builder.finish_node();
+    builder.dedent();
+    builder.finish_node();
+    let root = to_syntax(builder);
+
+    // The root sits at the document margin, with no active indentation.
+    assert_eq!(indent_level(&root), 0);
+    assert!(indent_prefix(&root).is_empty());
+
+    // The nested node lives under one live Indent, so it inherits its string.
+    let inner = SyntaxNode2D::new(root.inner().children().next().unwrap().clone());
+    assert_eq!(indent_level(&inner), 1);
+    assert_eq!(indent_prefix(&inner), "  ");
+}
+
+/**************************************************************/
+
+#[test]
+fn test_with_indent_scopes_auto_indents() {
+    // A scoped node kind auto-emits the opening Indent and the balancing Dedent,
+    // so the parser never touches indent/dedent by hand.
+    let mut builder: Builder<TestSyntax> = Builder::with_indent_scopes([TestSyntax::Text], "    ");
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "a");
+    builder.newline();
+    builder.start_node(TestSyntax::Text);
+    builder.token(TestSyntax::Text, "b");
+    builder.newline();
+    builder.token(TestSyntax::Text, "c");
+    builder.finish_node();
+    builder.newline();
+    builder.token(TestSyntax::Text, "d");
+    builder.finish_node();
+    let tree = builder.red();
+
+    assert_eq!(tree.to_string(), "a\n    b\n    c\nd");
+}
+
+/**************************************************************/
+
+#[test]
+fn test_insert_child_dedents_block_to_shallower_depth() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "a");
+    builder.finish_node();
+    let tree = builder.red();
+
+    // A block that carries one leading indent of its own (base depth 1).
+    let mut snippet_builder: Builder<TestSyntax> = Builder::new();
+    snippet_builder.start_node(TestSyntax::Text);
+    snippet_builder.indent("  ");
+    snippet_builder.token(TestSyntax::Text, "x");
+    snippet_builder.newline();
+    snippet_builder.token(TestSyntax::Text, "y");
+    snippet_builder.dedent();
+    snippet_builder.finish_node();
+    let snippet = snippet_builder.red();
+
+    // Inserted at the document margin (target depth 0 < base 1), the block's
+    // surplus indent is stripped so its newline renders at column zero rather
+    // than popping the surrounding context.
+    let inserted = tree.insert_child(InsertPosition::Last, &snippet);
+    assert_eq!(inserted.to_string(), "ax\ny");
+}
+
+/**************************************************************/
+
+#[test]
+fn test_ted_rehome_dedents_block_to_shallower_depth() {
+    let mut builder: Builder<TestSyntax> = Builder::new();
+    builder.start_node(TestSyntax::Root);
+    builder.token(TestSyntax::Text, "a");
+    builder.finish_node();
+    let tree = builder.red();
+
+    // A block carrying one leading indent of its own.
+    let mut snippet_builder: Builder<TestSyntax> = Builder::new();
+    snippet_builder.start_node(TestSyntax::Text);
+    snippet_builder.indent("  ");
+    snippet_builder.token(TestSyntax::Text, "x");
+    snippet_builder.newline();
+    snippet_builder.token(TestSyntax::Text, "y");
+    snippet_builder.dedent();
+    snippet_builder.finish_node();
+    let snippet = snippet_builder.red();
+
+    // Re-homed to the margin, the surplus indent is dropped rather than the
+    // surrounding context being dedented around the block.
+    let out = ted::insert_children(&tree, InsertPosition::Last, [snippet.green().clone()]);
+    assert_eq!(out.to_string(), "ax\ny");
+}